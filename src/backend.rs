@@ -0,0 +1,123 @@
+//! Platform backend abstraction.
+//!
+//! The `unix`, `windows` and `redox` modules each expose the same set of
+//! free functions (`open`, `lock`/`try_lock`, the shared-lock and
+//! byte-range variants, `query_lock`, `lock_with_timeout`, `unlock`,
+//! `remove`, `close`) selected by `cfg`. [`LockBackend`] captures that shape
+//! as a trait so a new target only has to provide one impl instead of
+//! copy-pasting an entire module.
+
+use core::time::Duration;
+
+/// Abstracts the low-level primitives needed to open, lock and remove a
+/// lock file on a given platform.
+pub trait LockBackend {
+    /// A type representing an open file descriptor/handle.
+    type FileDesc: Copy;
+
+    /// An IO error.
+    type Error;
+
+    /// Describes the process holding a lock that conflicts with a query, as
+    /// reported by [`query_lock`](LockBackend::query_lock)/
+    /// [`query_lock_range`](LockBackend::query_lock_range).
+    type LockHolder;
+
+    /// Opens a file with only purpose of locking it. Creates it if it does
+    /// not exist. Path must not contain a nul-byte in the middle, but a
+    /// nul-byte in the end (and only in the end) is allowed.
+    fn open(path: &[u8]) -> Result<Self::FileDesc, Self::Error>;
+
+    /// Tries to lock a file exclusively and blocks until it is possible to
+    /// lock.
+    fn lock(fd: Self::FileDesc) -> Result<(), Self::Error>;
+
+    /// Tries to lock a file exclusively but returns as soon as possible if
+    /// already locked.
+    fn try_lock(fd: Self::FileDesc) -> Result<bool, Self::Error>;
+
+    /// Tries to lock a file with a shared (read) lock and blocks until it is
+    /// possible to lock.
+    fn lock_shared(fd: Self::FileDesc) -> Result<(), Self::Error>;
+
+    /// Tries to lock a file with a shared (read) lock but returns as soon as
+    /// possible if already exclusively locked.
+    fn try_lock_shared(fd: Self::FileDesc) -> Result<bool, Self::Error>;
+
+    /// Locks a byte range `[offset, offset + len)` of the file exclusively
+    /// and blocks until it is possible to lock. A `len` of `0` locks from
+    /// `offset` to the end of the file.
+    fn lock_range(
+        fd: Self::FileDesc,
+        offset: u64,
+        len: u64,
+    ) -> Result<(), Self::Error>;
+
+    /// Tries to exclusively lock a byte range `[offset, offset + len)` of
+    /// the file, returning as soon as possible if already locked.
+    fn try_lock_range(
+        fd: Self::FileDesc,
+        offset: u64,
+        len: u64,
+    ) -> Result<bool, Self::Error>;
+
+    /// Locks a byte range `[offset, offset + len)` of the file with a shared
+    /// (read) lock and blocks until it is possible to lock.
+    fn lock_range_shared(
+        fd: Self::FileDesc,
+        offset: u64,
+        len: u64,
+    ) -> Result<(), Self::Error>;
+
+    /// Tries to lock a byte range `[offset, offset + len)` of the file with
+    /// a shared (read) lock, returning as soon as possible if already
+    /// exclusively locked.
+    fn try_lock_range_shared(
+        fd: Self::FileDesc,
+        offset: u64,
+        len: u64,
+    ) -> Result<bool, Self::Error>;
+
+    /// Unlocks the file.
+    fn unlock(fd: Self::FileDesc) -> Result<(), Self::Error>;
+
+    /// Unlocks the byte range `[offset, offset + len)` of the file. A `len`
+    /// of `0` unlocks from `offset` to the end of the file.
+    fn unlock_range(
+        fd: Self::FileDesc,
+        offset: u64,
+        len: u64,
+    ) -> Result<(), Self::Error>;
+
+    /// Blocks until the file can be locked exclusively, or returns
+    /// `Ok(false)` once `timeout` has elapsed without acquiring the lock.
+    fn lock_with_timeout(
+        fd: Self::FileDesc,
+        timeout: Duration,
+    ) -> Result<bool, Self::Error>;
+
+    /// Reports whether locking the whole file exclusively would conflict
+    /// with a lock already held by another process, and if so, which
+    /// process holds it. Returns `Ok(None)` if the file is currently
+    /// lockable.
+    fn query_lock(
+        fd: Self::FileDesc,
+    ) -> Result<Option<Self::LockHolder>, Self::Error>;
+
+    /// Reports whether exclusively locking the byte range `[offset, offset +
+    /// len)` would conflict with a lock already held by another process,
+    /// and if so, which process holds it. Returns `Ok(None)` if the range is
+    /// currently lockable.
+    fn query_lock_range(
+        fd: Self::FileDesc,
+        offset: u64,
+        len: u64,
+    ) -> Result<Option<Self::LockHolder>, Self::Error>;
+
+    /// Removes a file. Path must not contain a nul-byte in the middle, but a
+    /// nul-byte in the end (and only in the end) is allowed.
+    fn remove(path: &[u8]) -> Result<(), Self::Error>;
+
+    /// Closes the file.
+    fn close(fd: Self::FileDesc);
+}
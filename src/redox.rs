@@ -0,0 +1,253 @@
+//! Redox OS backend. Selected by `cfg(target_os = "redox")`.
+//!
+//! An earlier version of this module hand-rolled advisory locking directly
+//! on top of the `redox_syscall` crate's raw primitives (a `make_flock`
+//! helper building a `syscall::flock`, applied through `syscall::fcntl` with
+//! `F_SETLK`/`F_SETLKW`). That doesn't compile: `redox_syscall`, in both the
+//! 0.3.5 and the current 0.9.1 release, has no `flock` type and no
+//! `F_SETLK`/`F_SETLKW`/`F_GETLK` constants at all -- its `fcntl` only
+//! covers descriptor flags (`F_DUPFD`/`F_GETFD`/`F_SETFD`/`F_GETFL`/
+//! `F_SETFL`), not record locking.
+//!
+//! Record locking on Redox is actually implemented by relibc, Redox's
+//! POSIX-compatible libc, with `fcntl`/`flock` semantics matching Linux's.
+//! Redox is part of Rust's `unix` target family, and the `libc` crate
+//! already exposes relibc's locking API -- the same one the
+//! [`unix`](crate::unix) backend is built on. So rather than hand-roll raw
+//! syscalls that don't exist, this backend just forwards to `unix`.
+
+use core::time::Duration;
+
+use crate::{backend::LockBackend, unix, unix::IntoOsString};
+
+/// A type representing file descriptor on Redox.
+pub type FileDesc = unix::FileDesc;
+
+/// An IO error.
+pub type Error = unix::Error;
+
+/// Describes the process currently holding a conflicting lock, as reported
+/// by [`query_lock`]/[`query_lock_range`]. See [`unix::LockHolder`].
+pub type LockHolder = unix::LockHolder;
+
+/// Opens a file with only purpose of locking it. Creates it if it does not
+/// exist. Opened for both reading and writing, so both the exclusive-lock
+/// and shared-lock functions work through this single `open` call.
+pub fn open(path: &[u8]) -> Result<FileDesc, Error> {
+    let os_string = unix::make_os_str(path)?.into_os_string()?;
+    unix::open(os_string.as_ref(), unix::AccessMode::ReadWrite)
+}
+
+/// Tries to lock a file exclusively and blocks until it is possible to lock.
+pub fn lock(fd: FileDesc) -> Result<(), Error> {
+    unix::lock(fd)
+}
+
+/// Tries to lock a file exclusively but returns as soon as possible if
+/// already locked.
+pub fn try_lock(fd: FileDesc) -> Result<bool, Error> {
+    unix::try_lock(fd)
+}
+
+/// Tries to lock a file with a shared (read) lock and blocks until it is
+/// possible to lock.
+pub fn lock_shared(fd: FileDesc) -> Result<(), Error> {
+    unix::lock_shared(fd)
+}
+
+/// Tries to lock a file with a shared (read) lock but returns as soon as
+/// possible if already exclusively locked.
+pub fn try_lock_shared(fd: FileDesc) -> Result<bool, Error> {
+    unix::try_lock_shared(fd)
+}
+
+/// Unlocks the file.
+pub fn unlock(fd: FileDesc) -> Result<(), Error> {
+    unix::unlock(fd)
+}
+
+/// Locks a byte range `[offset, offset + len)` of the file exclusively and
+/// blocks until it is possible to lock. A `len` of `0` locks from `offset`
+/// to the end of the file.
+pub fn lock_range(fd: FileDesc, offset: u64, len: u64) -> Result<(), Error> {
+    unix::lock_range(fd, offset, len)
+}
+
+/// Tries to exclusively lock a byte range `[offset, offset + len)` of the
+/// file, returning as soon as possible if already locked.
+pub fn try_lock_range(
+    fd: FileDesc,
+    offset: u64,
+    len: u64,
+) -> Result<bool, Error> {
+    unix::try_lock_range(fd, offset, len)
+}
+
+/// Locks a byte range `[offset, offset + len)` of the file with a shared
+/// (read) lock and blocks until it is possible to lock.
+pub fn lock_range_shared(
+    fd: FileDesc,
+    offset: u64,
+    len: u64,
+) -> Result<(), Error> {
+    unix::lock_range_shared(fd, offset, len)
+}
+
+/// Tries to lock a byte range `[offset, offset + len)` of the file with a
+/// shared (read) lock, returning as soon as possible if already exclusively
+/// locked.
+pub fn try_lock_range_shared(
+    fd: FileDesc,
+    offset: u64,
+    len: u64,
+) -> Result<bool, Error> {
+    unix::try_lock_range_shared(fd, offset, len)
+}
+
+/// Unlocks the byte range `[offset, offset + len)` of the file. A `len` of
+/// `0` unlocks from `offset` to the end of the file.
+pub fn unlock_range(fd: FileDesc, offset: u64, len: u64) -> Result<(), Error> {
+    unix::unlock_range(fd, offset, len)
+}
+
+/// Blocks until the file can be locked exclusively, or returns `Ok(false)`
+/// once `timeout` has elapsed without acquiring the lock.
+pub fn lock_with_timeout(
+    fd: FileDesc,
+    timeout: Duration,
+) -> Result<bool, Error> {
+    unix::lock_with_timeout(fd, timeout)
+}
+
+/// Reports whether locking the whole file exclusively would conflict with a
+/// lock already held by another process, and if so, which process holds it.
+/// Returns `Ok(None)` if the file is currently lockable.
+pub fn query_lock(fd: FileDesc) -> Result<Option<LockHolder>, Error> {
+    unix::query_lock(fd)
+}
+
+/// Reports whether exclusively locking the byte range `[offset, offset +
+/// len)` would conflict with a lock already held by another process, and if
+/// so, which process holds it. Returns `Ok(None)` if the range is currently
+/// lockable.
+pub fn query_lock_range(
+    fd: FileDesc,
+    offset: u64,
+    len: u64,
+) -> Result<Option<LockHolder>, Error> {
+    unix::query_lock_range(fd, offset, len)
+}
+
+/// Removes a file. Path must not contain a nul-byte in the middle, but a
+/// nul-byte in the end (and only in the end) is allowed.
+pub fn remove(path: &[u8]) -> Result<(), Error> {
+    let os_string = unix::make_os_str(path)?.into_os_string()?;
+    unix::remove(os_string.as_ref())
+}
+
+/// Closes the file.
+pub fn close(fd: FileDesc) {
+    unix::close(fd)
+}
+
+/// Marker type selecting this module's [`LockBackend`] implementation.
+pub struct Redox;
+
+impl LockBackend for Redox {
+    type FileDesc = FileDesc;
+    type Error = Error;
+    type LockHolder = LockHolder;
+
+    fn open(path: &[u8]) -> Result<Self::FileDesc, Self::Error> {
+        open(path)
+    }
+
+    fn lock(fd: Self::FileDesc) -> Result<(), Self::Error> {
+        lock(fd)
+    }
+
+    fn try_lock(fd: Self::FileDesc) -> Result<bool, Self::Error> {
+        try_lock(fd)
+    }
+
+    fn lock_shared(fd: Self::FileDesc) -> Result<(), Self::Error> {
+        lock_shared(fd)
+    }
+
+    fn try_lock_shared(fd: Self::FileDesc) -> Result<bool, Self::Error> {
+        try_lock_shared(fd)
+    }
+
+    fn lock_range(
+        fd: Self::FileDesc,
+        offset: u64,
+        len: u64,
+    ) -> Result<(), Self::Error> {
+        lock_range(fd, offset, len)
+    }
+
+    fn try_lock_range(
+        fd: Self::FileDesc,
+        offset: u64,
+        len: u64,
+    ) -> Result<bool, Self::Error> {
+        try_lock_range(fd, offset, len)
+    }
+
+    fn lock_range_shared(
+        fd: Self::FileDesc,
+        offset: u64,
+        len: u64,
+    ) -> Result<(), Self::Error> {
+        lock_range_shared(fd, offset, len)
+    }
+
+    fn try_lock_range_shared(
+        fd: Self::FileDesc,
+        offset: u64,
+        len: u64,
+    ) -> Result<bool, Self::Error> {
+        try_lock_range_shared(fd, offset, len)
+    }
+
+    fn unlock(fd: Self::FileDesc) -> Result<(), Self::Error> {
+        unlock(fd)
+    }
+
+    fn unlock_range(
+        fd: Self::FileDesc,
+        offset: u64,
+        len: u64,
+    ) -> Result<(), Self::Error> {
+        unlock_range(fd, offset, len)
+    }
+
+    fn lock_with_timeout(
+        fd: Self::FileDesc,
+        timeout: Duration,
+    ) -> Result<bool, Self::Error> {
+        lock_with_timeout(fd, timeout)
+    }
+
+    fn query_lock(
+        fd: Self::FileDesc,
+    ) -> Result<Option<Self::LockHolder>, Self::Error> {
+        query_lock(fd)
+    }
+
+    fn query_lock_range(
+        fd: Self::FileDesc,
+        offset: u64,
+        len: u64,
+    ) -> Result<Option<Self::LockHolder>, Self::Error> {
+        query_lock_range(fd, offset, len)
+    }
+
+    fn remove(path: &[u8]) -> Result<(), Self::Error> {
+        remove(path)
+    }
+
+    fn close(fd: Self::FileDesc) {
+        close(fd)
+    }
+}
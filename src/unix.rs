@@ -5,16 +5,13 @@ use core::{
     str,
 };
 
-use core::{mem::transmute, ops::Deref, ptr::NonNull};
-
-extern "C" {
-    /// [Linux man page](https://linux.die.net/man/3/lockf)
-    fn lockf(
-        fd: libc::c_int,
-        cmd: libc::c_int,
-        offset: libc::off_t,
-    ) -> libc::c_int;
-}
+use core::{
+    mem,
+    mem::transmute,
+    ops::Deref,
+    ptr::{self, NonNull},
+    time::Duration,
+};
 
 /// A type representing file descriptor on Unix.
 pub type FileDesc = libc::c_int;
@@ -234,13 +231,14 @@ impl ToOsStr for str {
     }
 }
 
-/// Path must not contain a nul-byte in the middle, but a nul-byte in the end
-/// (and only in the end) is allowed, which in this case no extra allocation
-/// will be made. Otherwise, an extra allocation is made.
-fn make_os_str(slice: &[u8]) -> Result<EitherOsStr, Error> {
+/// Path must not contain a nul-byte in the middle (an embedded nul-byte
+/// causes this to return an error), but a nul-byte in the end (and only in
+/// the end) is allowed, which in this case no extra allocation will be made.
+/// Otherwise, an extra allocation is made.
+pub(crate) fn make_os_str(slice: &[u8]) -> Result<EitherOsStr, Error> {
     if let Some((&last, init)) = slice.split_last() {
         if init.contains(&0) {
-            panic!("Path to file cannot contain nul-byte in the middle");
+            return Err(Error::from_raw_os_error(libc::EINVAL));
         }
         if last == 0 {
             return Ok(EitherOsStr::Borrowed(unsafe { transmute(&slice[0]) }));
@@ -266,15 +264,34 @@ fn make_os_str(slice: &[u8]) -> Result<EitherOsStr, Error> {
     Ok(EitherOsStr::Owned(OsString { alloc }))
 }
 
+/// Which access mode to open the file to be locked with. Shared locks
+/// require the file descriptor to be readable, so `Read` or `ReadWrite` must
+/// be used with [`lock_shared`]/[`try_lock_shared`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessMode {
+    /// Open for reading only.
+    Read,
+    /// Open for writing only. Sufficient for the exclusive-lock functions
+    /// `lock`/`try_lock`.
+    Write,
+    /// Open for both reading and writing.
+    ReadWrite,
+}
+
 /// Opens a file with only purpose of locking it. Creates it if it does not
 /// exist. Path must not contain a nul-byte in the middle, but a nul-byte in the
 /// end (and only in the end) is allowed, which in this case no extra allocation
 /// will be made. Otherwise, an extra allocation is made.
-pub fn open(path: &OsStr) -> Result<FileDesc, Error> {
+pub fn open(path: &OsStr, access: AccessMode) -> Result<FileDesc, Error> {
+    let access_flags = match access {
+        AccessMode::Read => libc::O_RDONLY,
+        AccessMode::Write => libc::O_WRONLY,
+        AccessMode::ReadWrite => libc::O_RDWR,
+    };
     let fd = unsafe {
         libc::open(
             path.phantom.as_ptr(),
-            libc::O_WRONLY | libc::O_CLOEXEC | libc::O_CREAT,
+            access_flags | libc::O_CLOEXEC | libc::O_CREAT,
             libc::S_IRUSR | libc::S_IWUSR | libc::S_IRGRP | libc::S_IROTH,
         )
     };
@@ -286,24 +303,36 @@ pub fn open(path: &OsStr) -> Result<FileDesc, Error> {
     }
 }
 
-/// Tries to lock a file and blocks until it is possible to lock.
-pub fn lock(fd: FileDesc) -> Result<(), Error> {
-    let res = unsafe { lockf(fd, libc::F_LOCK, 0) };
-    if res == 0 {
-        Ok(())
-    } else {
-        Err(Error::last_os_error())
-    }
+/// Fills a `flock` describing a lock of the given type over the byte range
+/// `[start, start + len)`. A `len` of `0` means "to the end of the file",
+/// matching `fcntl`'s own convention.
+fn make_flock(l_type: libc::c_short, start: u64, len: u64) -> libc::flock {
+    let mut flock: libc::flock = unsafe { mem::zeroed() };
+    flock.l_type = l_type;
+    flock.l_whence = libc::SEEK_SET as libc::c_short;
+    flock.l_start = start as libc::off_t;
+    flock.l_len = len as libc::off_t;
+    flock
 }
 
-/// Tries to lock a file but returns as soon as possible if already locked.
-pub fn try_lock(fd: FileDesc) -> Result<bool, Error> {
-    let res = unsafe { lockf(fd, libc::F_TLOCK, 0) };
+/// Applies (or releases) an `fcntl` lock over `[start, start + len)`,
+/// blocking if `blocking` is set, otherwise failing fast if the lock is
+/// already held elsewhere.
+fn set_lock(
+    fd: FileDesc,
+    l_type: libc::c_short,
+    start: u64,
+    len: u64,
+    blocking: bool,
+) -> Result<bool, Error> {
+    let mut flock = make_flock(l_type, start, len);
+    let cmd = if blocking { libc::F_SETLKW } else { libc::F_SETLK };
+    let res = unsafe { libc::fcntl(fd, cmd, &mut flock as *mut libc::flock) };
     if res == 0 {
         Ok(true)
     } else {
         let err = unsafe { *libc::__errno_location() };
-        if err == libc::EACCES || err == libc::EAGAIN {
+        if !blocking && (err == libc::EACCES || err == libc::EAGAIN) {
             Ok(false)
         } else {
             Err(Error::from_raw_os_error(err as i32))
@@ -311,16 +340,187 @@ pub fn try_lock(fd: FileDesc) -> Result<bool, Error> {
     }
 }
 
+/// Tries to lock a file exclusively and blocks until it is possible to lock.
+pub fn lock(fd: FileDesc) -> Result<(), Error> {
+    set_lock(fd, libc::F_WRLCK as libc::c_short, 0, 0, true).map(|_| ())
+}
+
+/// Tries to lock a file exclusively but returns as soon as possible if
+/// already locked.
+pub fn try_lock(fd: FileDesc) -> Result<bool, Error> {
+    set_lock(fd, libc::F_WRLCK as libc::c_short, 0, 0, false)
+}
+
+/// Tries to lock a file with a shared (read) lock and blocks until it is
+/// possible to lock. The file descriptor must have been opened with
+/// [`AccessMode::Read`] or [`AccessMode::ReadWrite`].
+pub fn lock_shared(fd: FileDesc) -> Result<(), Error> {
+    set_lock(fd, libc::F_RDLCK as libc::c_short, 0, 0, true).map(|_| ())
+}
+
+/// Tries to lock a file with a shared (read) lock but returns as soon as
+/// possible if already exclusively locked.
+pub fn try_lock_shared(fd: FileDesc) -> Result<bool, Error> {
+    set_lock(fd, libc::F_RDLCK as libc::c_short, 0, 0, false)
+}
+
 /// Unlocks the file.
 pub fn unlock(fd: FileDesc) -> Result<(), Error> {
-    let res = unsafe { lockf(fd, libc::F_ULOCK, 0) };
-    if res == 0 {
-        Ok(())
+    set_lock(fd, libc::F_UNLCK as libc::c_short, 0, 0, true).map(|_| ())
+}
+
+/// Locks a byte range `[offset, offset + len)` of the file exclusively and
+/// blocks until it is possible to lock. A `len` of `0` locks from `offset` to
+/// the end of the file.
+pub fn lock_range(fd: FileDesc, offset: u64, len: u64) -> Result<(), Error> {
+    set_lock(fd, libc::F_WRLCK as libc::c_short, offset, len, true)
+        .map(|_| ())
+}
+
+/// Tries to exclusively lock a byte range `[offset, offset + len)` of the
+/// file, returning as soon as possible if already locked. A `len` of `0`
+/// locks from `offset` to the end of the file.
+pub fn try_lock_range(
+    fd: FileDesc,
+    offset: u64,
+    len: u64,
+) -> Result<bool, Error> {
+    set_lock(fd, libc::F_WRLCK as libc::c_short, offset, len, false)
+}
+
+/// Locks a byte range `[offset, offset + len)` of the file with a shared
+/// (read) lock and blocks until it is possible to lock. A `len` of `0` locks
+/// from `offset` to the end of the file.
+pub fn lock_range_shared(
+    fd: FileDesc,
+    offset: u64,
+    len: u64,
+) -> Result<(), Error> {
+    set_lock(fd, libc::F_RDLCK as libc::c_short, offset, len, true)
+        .map(|_| ())
+}
+
+/// Tries to lock a byte range `[offset, offset + len)` of the file with a
+/// shared (read) lock, returning as soon as possible if already exclusively
+/// locked. A `len` of `0` locks from `offset` to the end of the file.
+pub fn try_lock_range_shared(
+    fd: FileDesc,
+    offset: u64,
+    len: u64,
+) -> Result<bool, Error> {
+    set_lock(fd, libc::F_RDLCK as libc::c_short, offset, len, false)
+}
+
+/// Unlocks a byte range `[offset, offset + len)` of the file. A `len` of `0`
+/// unlocks from `offset` to the end of the file.
+pub fn unlock_range(fd: FileDesc, offset: u64, len: u64) -> Result<(), Error> {
+    set_lock(fd, libc::F_UNLCK as libc::c_short, offset, len, true)
+        .map(|_| ())
+}
+
+/// Initial delay between retries in [`lock_with_timeout`]'s backoff.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(1);
+
+/// Upper bound on the delay between retries in [`lock_with_timeout`]'s
+/// backoff.
+const MAX_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Returns the current value of `CLOCK_MONOTONIC`, in nanoseconds.
+fn monotonic_now_nanos() -> i64 {
+    let mut ts: libc::timespec = unsafe { mem::zeroed() };
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) };
+    ts.tv_sec as i64 * 1_000_000_000 + ts.tv_nsec as i64
+}
+
+/// Sleeps for the given duration via `nanosleep`, ignoring early wakeups
+/// from signal interruption.
+fn sleep(duration: Duration) {
+    let ts = libc::timespec {
+        tv_sec: duration.as_secs() as libc::time_t,
+        tv_nsec: libc::c_long::from(duration.subsec_nanos() as i32),
+    };
+    unsafe { libc::nanosleep(&ts, ptr::null_mut()) };
+}
+
+/// Blocks until the file can be locked exclusively, or returns `Ok(false)`
+/// once `timeout` has elapsed without acquiring the lock. Retries the
+/// non-blocking `try_lock` with a capped exponential backoff between
+/// attempts, sleeping via `nanosleep` and checking the deadline against
+/// `CLOCK_MONOTONIC`.
+pub fn lock_with_timeout(
+    fd: FileDesc,
+    timeout: Duration,
+) -> Result<bool, Error> {
+    let timeout_nanos = timeout.as_nanos().min(i64::max_value() as u128) as i64;
+    let deadline = monotonic_now_nanos().saturating_add(timeout_nanos);
+    let mut backoff = INITIAL_BACKOFF;
+
+    loop {
+        if try_lock(fd)? {
+            return Ok(true);
+        }
+        if monotonic_now_nanos() >= deadline {
+            return Ok(false);
+        }
+        sleep(backoff);
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// Describes the process currently holding a lock that would conflict with
+/// an exclusive lock, as reported by [`query_lock`]/[`query_lock_range`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockHolder {
+    /// The PID of the process holding the lock.
+    pub pid: libc::pid_t,
+    /// Whether the held lock is shared (read) or exclusive (write).
+    pub shared: bool,
+}
+
+/// Reports whether an exclusive lock over `[start, start + len)` would
+/// conflict with a lock already held by another process, and if so, which
+/// process holds it. Returns `Ok(None)` if the region is currently lockable.
+fn get_lock(
+    fd: FileDesc,
+    start: u64,
+    len: u64,
+) -> Result<Option<LockHolder>, Error> {
+    let mut flock = make_flock(libc::F_WRLCK as libc::c_short, start, len);
+    let res =
+        unsafe { libc::fcntl(fd, libc::F_GETLK, &mut flock as *mut libc::flock) };
+    if res != 0 {
+        return Err(Error::last_os_error());
+    }
+
+    if flock.l_type as libc::c_int == libc::F_UNLCK {
+        Ok(None)
     } else {
-        Err(Error::last_os_error())
+        Ok(Some(LockHolder {
+            pid: flock.l_pid,
+            shared: flock.l_type as libc::c_int == libc::F_RDLCK,
+        }))
     }
 }
 
+/// Reports whether locking the whole file exclusively would conflict with a
+/// lock already held by another process, and if so, which process holds it.
+/// Returns `Ok(None)` if the file is currently lockable.
+pub fn query_lock(fd: FileDesc) -> Result<Option<LockHolder>, Error> {
+    get_lock(fd, 0, 0)
+}
+
+/// Reports whether exclusively locking the byte range `[offset, offset +
+/// len)` would conflict with a lock already held by another process, and if
+/// so, which process holds it. Returns `Ok(None)` if the range is currently
+/// lockable. A `len` of `0` queries from `offset` to the end of the file.
+pub fn query_lock_range(
+    fd: FileDesc,
+    offset: u64,
+    len: u64,
+) -> Result<Option<LockHolder>, Error> {
+    get_lock(fd, offset, len)
+}
+
 /// Removes a file. Path must not contain a nul-byte in the middle, but a
 /// nul-byte in the end (and only in the end) is allowed, which in this case no
 /// extra allocation will be made. Otherwise, an extra allocation is made.
@@ -336,4 +536,383 @@ pub fn remove(path: &OsStr) -> Result<(), Error> {
 /// Closes the file.
 pub fn close(fd: FileDesc) {
     unsafe { libc::close(fd) };
+}
+
+/// Marker type selecting this module's [`LockBackend`](crate::backend::LockBackend)
+/// implementation.
+pub struct Unix;
+
+impl crate::backend::LockBackend for Unix {
+    type FileDesc = FileDesc;
+    type Error = Error;
+    type LockHolder = LockHolder;
+
+    fn open(path: &[u8]) -> Result<Self::FileDesc, Self::Error> {
+        // `ReadWrite`, not `Write`: `lock_shared`/`try_lock_shared` and
+        // their range variants take an `F_RDLCK`, which `fcntl` rejects
+        // with `EBADF` on a write-only descriptor. Going through
+        // `LockBackend` must support the whole trait, not just the
+        // exclusive-lock subset.
+        let os_string = make_os_str(path)?.into_os_string()?;
+        open(os_string.as_ref(), AccessMode::ReadWrite)
+    }
+
+    fn lock(fd: Self::FileDesc) -> Result<(), Self::Error> {
+        lock(fd)
+    }
+
+    fn try_lock(fd: Self::FileDesc) -> Result<bool, Self::Error> {
+        try_lock(fd)
+    }
+
+    fn lock_shared(fd: Self::FileDesc) -> Result<(), Self::Error> {
+        lock_shared(fd)
+    }
+
+    fn try_lock_shared(fd: Self::FileDesc) -> Result<bool, Self::Error> {
+        try_lock_shared(fd)
+    }
+
+    fn lock_range(
+        fd: Self::FileDesc,
+        offset: u64,
+        len: u64,
+    ) -> Result<(), Self::Error> {
+        lock_range(fd, offset, len)
+    }
+
+    fn try_lock_range(
+        fd: Self::FileDesc,
+        offset: u64,
+        len: u64,
+    ) -> Result<bool, Self::Error> {
+        try_lock_range(fd, offset, len)
+    }
+
+    fn lock_range_shared(
+        fd: Self::FileDesc,
+        offset: u64,
+        len: u64,
+    ) -> Result<(), Self::Error> {
+        lock_range_shared(fd, offset, len)
+    }
+
+    fn try_lock_range_shared(
+        fd: Self::FileDesc,
+        offset: u64,
+        len: u64,
+    ) -> Result<bool, Self::Error> {
+        try_lock_range_shared(fd, offset, len)
+    }
+
+    fn unlock(fd: Self::FileDesc) -> Result<(), Self::Error> {
+        unlock(fd)
+    }
+
+    fn unlock_range(
+        fd: Self::FileDesc,
+        offset: u64,
+        len: u64,
+    ) -> Result<(), Self::Error> {
+        unlock_range(fd, offset, len)
+    }
+
+    fn lock_with_timeout(
+        fd: Self::FileDesc,
+        timeout: Duration,
+    ) -> Result<bool, Self::Error> {
+        lock_with_timeout(fd, timeout)
+    }
+
+    fn query_lock(
+        fd: Self::FileDesc,
+    ) -> Result<Option<Self::LockHolder>, Self::Error> {
+        query_lock(fd)
+    }
+
+    fn query_lock_range(
+        fd: Self::FileDesc,
+        offset: u64,
+        len: u64,
+    ) -> Result<Option<Self::LockHolder>, Self::Error> {
+        query_lock_range(fd, offset, len)
+    }
+
+    fn remove(path: &[u8]) -> Result<(), Self::Error> {
+        let os_string = make_os_str(path)?.into_os_string()?;
+        remove(os_string.as_ref())
+    }
+
+    fn close(fd: Self::FileDesc) {
+        close(fd)
+    }
+}
+
+// `fcntl` record locks are owned by the *process*, not the file
+// descriptor -- two descriptors opened by the same process never conflict
+// with each other. So the only way to exercise actual lock contention is
+// against a genuinely different process, which these tests get by
+// `fork`ing a child that takes (or observes) a lock and reports back
+// through a pipe or its exit status.
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// A path under the OS temp dir, unique per call, so parallel test runs
+    /// and repeated calls within one test don't collide.
+    fn unique_path(tag: &str) -> std::path::PathBuf {
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+        let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let mut path = std::env::temp_dir();
+        path.push(format!(
+            "fslock-unix-test-{}-{}-{}",
+            std::process::id(),
+            tag,
+            id
+        ));
+        path
+    }
+
+    fn open_path(path: &std::path::Path, access: AccessMode) -> FileDesc {
+        let os_string = path
+            .to_str()
+            .expect("test path must be utf8")
+            .to_os_str()
+            .expect("to_os_str")
+            .into_os_string()
+            .expect("into_os_string");
+        open(os_string.as_ref(), access).expect("open")
+    }
+
+    /// Forks a child that tries (non-blockingly) to take a lock of the
+    /// given kind on `fd`, exiting `0` if it got the lock and `1`
+    /// otherwise. Returns whether the child got the lock.
+    fn child_try_lock(fd: FileDesc, shared: bool) -> bool {
+        match unsafe { libc::fork() } {
+            -1 => panic!("fork failed: {}", Error::last_os_error()),
+            0 => {
+                let got = if shared {
+                    try_lock_shared(fd)
+                } else {
+                    try_lock(fd)
+                }
+                .expect("try_lock in child");
+                unsafe { libc::_exit(if got { 0 } else { 1 }) };
+            },
+            pid => child_exited_ok(pid),
+        }
+    }
+
+    /// Forks a child that tries (non-blockingly) to take an exclusive lock
+    /// over `[offset, offset + len)` of `fd`. Returns whether it got it.
+    fn child_try_lock_range(
+        fd: FileDesc,
+        offset: u64,
+        len: u64,
+    ) -> bool {
+        match unsafe { libc::fork() } {
+            -1 => panic!("fork failed: {}", Error::last_os_error()),
+            0 => {
+                let got = try_lock_range(fd, offset, len)
+                    .expect("try_lock_range in child");
+                unsafe { libc::_exit(if got { 0 } else { 1 }) };
+            },
+            pid => child_exited_ok(pid),
+        }
+    }
+
+    /// Forks a child that takes the given kind of whole-file lock on its
+    /// own descriptor for `path`, signals readiness by writing a byte to
+    /// `ready_w`, then sleeps for `hold` before exiting (releasing the lock
+    /// when its descriptor is closed). Returns the child's pid.
+    fn fork_lock_holder(
+        path: &std::path::Path,
+        shared: bool,
+        hold: Duration,
+        ready_w: libc::c_int,
+    ) -> libc::pid_t {
+        match unsafe { libc::fork() } {
+            -1 => panic!("fork failed: {}", Error::last_os_error()),
+            0 => {
+                let fd = open_path(path, AccessMode::ReadWrite);
+                if shared {
+                    lock_shared(fd).expect("lock_shared in child");
+                } else {
+                    lock(fd).expect("lock in child");
+                }
+                unsafe { libc::write(ready_w, [1u8].as_ptr() as _, 1) };
+                std::thread::sleep(hold);
+                unsafe { libc::_exit(0) };
+            },
+            pid => pid,
+        }
+    }
+
+    /// Waits for `pid` and reports whether it exited with status `0`.
+    fn child_exited_ok(pid: libc::pid_t) -> bool {
+        let mut status = 0;
+        unsafe { libc::waitpid(pid, &mut status, 0) };
+        libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0
+    }
+
+    /// Blocks until a byte is available on `ready_r`, signalling that the
+    /// corresponding `fork_lock_holder` child has taken its lock.
+    fn wait_ready(ready_r: libc::c_int) {
+        let mut buf = [0u8; 1];
+        let res =
+            unsafe { libc::read(ready_r, buf.as_mut_ptr() as _, 1) };
+        assert_eq!(res, 1, "readiness pipe closed unexpectedly");
+    }
+
+    #[test]
+    fn exclusive_and_shared_locks_interact_across_processes() {
+        let path = unique_path("shared-vs-exclusive");
+        let fd = open_path(&path, AccessMode::ReadWrite);
+
+        // No lock held yet: a child can take either kind.
+        assert!(child_try_lock(fd, false), "child should lock exclusively");
+        assert!(child_try_lock(fd, true), "child should lock shared");
+
+        // Exclusive lock blocks both exclusive and shared attempts.
+        lock(fd).expect("lock");
+        assert!(
+            !child_try_lock(fd, false),
+            "child should not lock exclusively while held exclusively"
+        );
+        assert!(
+            !child_try_lock(fd, true),
+            "child should not lock shared while held exclusively"
+        );
+        unlock(fd).expect("unlock");
+
+        // A shared lock only blocks exclusive attempts, not other shared
+        // ones.
+        lock_shared(fd).expect("lock_shared");
+        assert!(
+            !child_try_lock(fd, false),
+            "child should not lock exclusively while held shared"
+        );
+        assert!(
+            child_try_lock(fd, true),
+            "child should lock shared alongside another shared lock"
+        );
+        unlock(fd).expect("unlock");
+
+        close(fd);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn byte_ranges_only_conflict_on_overlap() {
+        let path = unique_path("byte-range");
+        let fd = open_path(&path, AccessMode::ReadWrite);
+
+        lock_range(fd, 0, 10).expect("lock_range");
+        assert!(
+            child_try_lock_range(fd, 20, 10),
+            "non-overlapping range should be lockable"
+        );
+        assert!(
+            !child_try_lock_range(fd, 5, 10),
+            "overlapping range should not be lockable"
+        );
+        unlock_range(fd, 0, 10).expect("unlock_range");
+        assert!(
+            child_try_lock_range(fd, 5, 10),
+            "range should be lockable once unlocked"
+        );
+
+        close(fd);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn lock_with_timeout_expires_then_succeeds_once_free() {
+        let path = unique_path("timeout");
+        let fd = open_path(&path, AccessMode::ReadWrite);
+
+        let mut pipe = [0 as libc::c_int; 2];
+        assert_eq!(unsafe { libc::pipe(pipe.as_mut_ptr()) }, 0);
+        let [ready_r, ready_w] = pipe;
+
+        let child =
+            fork_lock_holder(&path, false, Duration::from_millis(300), ready_w);
+        unsafe { libc::close(ready_w) };
+        wait_ready(ready_r);
+        unsafe { libc::close(ready_r) };
+
+        let got = lock_with_timeout(fd, Duration::from_millis(50))
+            .expect("lock_with_timeout");
+        assert!(!got, "lock should still be held by the child");
+
+        let mut status = 0;
+        unsafe { libc::waitpid(child, &mut status, 0) };
+
+        let got = lock_with_timeout(fd, Duration::from_secs(1))
+            .expect("lock_with_timeout");
+        assert!(got, "lock should be free once the child exits");
+
+        close(fd);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn query_lock_reports_holder_pid_and_kind() {
+        let path = unique_path("query-lock");
+        let fd = open_path(&path, AccessMode::ReadWrite);
+
+        assert_eq!(query_lock(fd).expect("query_lock"), None);
+
+        let mut pipe = [0 as libc::c_int; 2];
+        assert_eq!(unsafe { libc::pipe(pipe.as_mut_ptr()) }, 0);
+        let [ready_r, ready_w] = pipe;
+        let child = fork_lock_holder(
+            &path,
+            false,
+            Duration::from_millis(200),
+            ready_w,
+        );
+        unsafe { libc::close(ready_w) };
+        wait_ready(ready_r);
+        unsafe { libc::close(ready_r) };
+
+        let holder = query_lock(fd).expect("query_lock").expect("should conflict");
+        assert_eq!(holder.pid, child);
+        assert!(!holder.shared);
+
+        let mut status = 0;
+        unsafe { libc::waitpid(child, &mut status, 0) };
+        assert_eq!(query_lock(fd).expect("query_lock"), None);
+
+        close(fd);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn lock_backend_open_supports_shared_locks() {
+        use crate::backend::LockBackend;
+
+        let path = unique_path("backend-shared");
+        let path_bytes = path.to_str().expect("test path must be utf8").as_bytes();
+
+        let fd = <Unix as LockBackend>::open(path_bytes).expect("open");
+
+        // A write-only fd fails F_RDLCK with EBADF, so `lock_shared` below
+        // would return an error if `open` regressed back to
+        // `AccessMode::Write`.
+        <Unix as LockBackend>::lock_shared(fd).expect("lock_shared");
+        assert!(
+            !child_try_lock(fd, false),
+            "child should not lock exclusively while held shared"
+        );
+        assert!(
+            child_try_lock(fd, true),
+            "child should lock shared alongside another shared lock"
+        );
+        <Unix as LockBackend>::unlock(fd).expect("unlock");
+
+        <Unix as LockBackend>::close(fd);
+        let _ = std::fs::remove_file(&path);
+    }
 }
\ No newline at end of file
@@ -22,20 +22,39 @@ use winapi::{
     },
 };
 
-use core::{mem::MaybeUninit, ptr};
+use core::{mem::MaybeUninit, ptr, time::Duration};
 use winapi::{
-    shared::winerror::ERROR_LOCK_VIOLATION,
+    shared::{
+        minwindef::FALSE,
+        winerror::{
+            ERROR_INVALID_NAME,
+            ERROR_IO_PENDING,
+            ERROR_LOCK_VIOLATION,
+            ERROR_NOT_SUPPORTED,
+            ERROR_OPERATION_ABORTED,
+        },
+    },
     um::{
         errhandlingapi::GetLastError,
-        fileapi::{LockFileEx, UnlockFileEx},
-        handleapi::CloseHandle,
+        fileapi::{CreateFileW, DeleteFileW, LockFileEx, OPEN_ALWAYS, UnlockFileEx},
+        handleapi::{CloseHandle, INVALID_HANDLE_VALUE},
+        ioapiset::{CancelIoEx, GetOverlappedResult},
         minwinbase::{
             LOCKFILE_EXCLUSIVE_LOCK,
             LOCKFILE_FAIL_IMMEDIATELY,
             LPOVERLAPPED,
             OVERLAPPED,
         },
-        winnt::HANDLE,
+        synchapi::CreateEventW,
+        winbase::{WAIT_OBJECT_0, WAIT_TIMEOUT},
+        winnt::{
+            FILE_ATTRIBUTE_NORMAL,
+            FILE_SHARE_READ,
+            FILE_SHARE_WRITE,
+            GENERIC_READ,
+            GENERIC_WRITE,
+            HANDLE,
+        },
     },
 };
 
@@ -134,31 +153,116 @@ where
     Ok(())
 }
 
+/// Maximum number of UTF-16 code units, including the terminating nul, that
+/// a path can be encoded into. This module has no heap allocator available,
+/// so paths are encoded into a fixed-size stack buffer instead.
+const MAX_WIDE_PATH_LEN: usize = 4096;
+
+/// Converts a UTF-8 path into a nul-terminated UTF-16 buffer suitable for
+/// `CreateFileW`/`DeleteFileW`, decoding code points and emitting surrogate
+/// pairs -- the reverse of what `write_wide_str` does when decoding.
+///
+/// Like the Unix `make_os_str`, a nul-byte in the middle of `path` is
+/// rejected; a single trailing nul-byte is allowed and stripped.
+fn to_wide_path(path: &[u8]) -> Result<[u16; MAX_WIDE_PATH_LEN], Error> {
+    let path = match path.split_last() {
+        Some((&0, init)) if !init.contains(&0) => init,
+        Some((_, init)) if init.contains(&0) => {
+            return Err(Error::from_raw_os_error(ERROR_INVALID_NAME as i32));
+        },
+        _ => path,
+    };
+
+    let text = core::str::from_utf8(path)
+        .map_err(|_| Error::from_raw_os_error(ERROR_INVALID_NAME as i32))?;
+
+    let mut buf = [0u16; MAX_WIDE_PATH_LEN];
+    let mut len = 0;
+    for ch in text.chars() {
+        let mut units = [0u16; 2];
+        for &unit in ch.encode_utf16(&mut units).iter() {
+            if len + 1 >= MAX_WIDE_PATH_LEN {
+                return Err(Error::from_raw_os_error(ERROR_INVALID_NAME as i32));
+            }
+            buf[len] = unit;
+            len += 1;
+        }
+    }
+    buf[len] = 0;
+
+    Ok(buf)
+}
+
 /// Opens a file with only purpose of locking it. Creates it if it does not
-/// exist. Path must not contain a nul-byte in the middle, but a nul-byte in the
-/// end (and only in the end) is allowed, which in this case no extra allocation
-/// will be made. Otherwise, an extra allocation is made.
+/// exist. Path must not contain a nul-byte in the middle (an embedded
+/// nul-byte causes this to return an error), but a nul-byte in the end (and
+/// only in the end) is allowed and stripped before encoding.
+///
+/// Opened for both reading and writing: `LockFileEx` requires read access to
+/// take a non-exclusive (shared) lock, so a write-only handle would fail
+/// [`lock_shared`]/[`try_lock_shared`] with `ERROR_ACCESS_DENIED`.
 pub fn open(path: &[u8]) -> Result<FileDesc, Error> {
-    unimplemented!()
+    let wide_path = to_wide_path(path)?;
+    let handle = unsafe {
+        CreateFileW(
+            wide_path.as_ptr(),
+            GENERIC_READ | GENERIC_WRITE,
+            FILE_SHARE_READ | FILE_SHARE_WRITE,
+            ptr::null_mut(),
+            OPEN_ALWAYS,
+            FILE_ATTRIBUTE_NORMAL,
+            ptr::null_mut(),
+        )
+    };
+
+    if handle != INVALID_HANDLE_VALUE {
+        Ok(handle)
+    } else {
+        Err(Error::last_os_error())
+    }
 }
 
-/// Tries to lock a file and blocks until it is possible to lock.
-pub fn lock(handle: FileDesc) -> Result<(), Error> {
+/// Splits a byte range into the low/high halves `LockFileEx`/`UnlockFileEx`
+/// and `OVERLAPPED` expect. A `len` of `0` is treated as "to the end of the
+/// file", matching the Unix `fcntl` convention, by locking the maximum
+/// possible range starting at `offset`.
+fn split_range(offset: u64, len: u64) -> (DWORD, DWORD, DWORD, DWORD) {
+    let len = if len == 0 { u64::max_value() - offset } else { len };
+    (
+        offset as DWORD,
+        (offset >> 32) as DWORD,
+        len as DWORD,
+        (len >> 32) as DWORD,
+    )
+}
+
+/// Tries to apply a lock over `[offset, offset + len)` and blocks until it is
+/// possible to lock. `flags` is passed through to `LockFileEx` and should
+/// include `LOCKFILE_EXCLUSIVE_LOCK` for an exclusive lock, or be left
+/// without it for a shared lock.
+fn lock_file(
+    handle: FileDesc,
+    flags: DWORD,
+    offset: u64,
+    len: u64,
+) -> Result<(), Error> {
+    let (offset_low, offset_high, len_low, len_high) =
+        split_range(offset, len);
     let mut overlapped: OVERLAPPED =
         unsafe { MaybeUninit::zeroed().assume_init() };
     unsafe {
-        overlapped.u.s_mut().Offset = 0;
-        overlapped.u.s_mut().OffsetHigh = 0;
+        overlapped.u.s_mut().Offset = offset_low;
+        overlapped.u.s_mut().OffsetHigh = offset_high;
     }
     overlapped.hEvent = ptr::null_mut();
 
     let res = unsafe {
         LockFileEx(
             handle,
-            LOCKFILE_EXCLUSIVE_LOCK,
+            flags,
             0,
-            DWORD::max_value(),
-            DWORD::max_value(),
+            len_low,
+            len_high,
             &mut overlapped as LPOVERLAPPED,
         )
     };
@@ -174,23 +278,31 @@ pub fn lock(handle: FileDesc) -> Result<(), Error> {
     }
 }
 
-/// Tries to lock a file but returns as soon as possible if already locked.
-pub fn try_lock(handle: FileDesc) -> Result<bool, Error> {
+/// Tries to apply a lock over `[offset, offset + len)` but returns as soon as
+/// possible if already locked. See [`lock_file`] for the meaning of `flags`.
+fn try_lock_file(
+    handle: FileDesc,
+    flags: DWORD,
+    offset: u64,
+    len: u64,
+) -> Result<bool, Error> {
+    let (offset_low, offset_high, len_low, len_high) =
+        split_range(offset, len);
     let mut overlapped: OVERLAPPED =
         unsafe { MaybeUninit::zeroed().assume_init() };
     unsafe {
-        overlapped.u.s_mut().Offset = 0;
-        overlapped.u.s_mut().OffsetHigh = 0;
+        overlapped.u.s_mut().Offset = offset_low;
+        overlapped.u.s_mut().OffsetHigh = offset_high;
     }
     overlapped.hEvent = ptr::null_mut();
 
     let res = unsafe {
         LockFileEx(
             handle,
-            LOCKFILE_EXCLUSIVE_LOCK | LOCKFILE_FAIL_IMMEDIATELY,
+            flags | LOCKFILE_FAIL_IMMEDIATELY,
             0,
-            DWORD::max_value(),
-            DWORD::max_value(),
+            len_low,
+            len_high,
             &mut overlapped as LPOVERLAPPED,
         )
     };
@@ -211,13 +323,92 @@ pub fn try_lock(handle: FileDesc) -> Result<bool, Error> {
     }
 }
 
+/// Tries to lock a file exclusively and blocks until it is possible to lock.
+pub fn lock(handle: FileDesc) -> Result<(), Error> {
+    lock_file(handle, LOCKFILE_EXCLUSIVE_LOCK, 0, 0)
+}
+
+/// Tries to lock a file exclusively but returns as soon as possible if
+/// already locked.
+pub fn try_lock(handle: FileDesc) -> Result<bool, Error> {
+    try_lock_file(handle, LOCKFILE_EXCLUSIVE_LOCK, 0, 0)
+}
+
+/// Tries to lock a file with a shared (read) lock and blocks until it is
+/// possible to lock.
+pub fn lock_shared(handle: FileDesc) -> Result<(), Error> {
+    lock_file(handle, 0, 0, 0)
+}
+
+/// Tries to lock a file with a shared (read) lock but returns as soon as
+/// possible if already exclusively locked.
+pub fn try_lock_shared(handle: FileDesc) -> Result<bool, Error> {
+    try_lock_file(handle, 0, 0, 0)
+}
+
+/// Locks a byte range `[offset, offset + len)` of the file exclusively and
+/// blocks until it is possible to lock. A `len` of `0` locks from `offset` to
+/// the end of the file.
+pub fn lock_range(
+    handle: FileDesc,
+    offset: u64,
+    len: u64,
+) -> Result<(), Error> {
+    lock_file(handle, LOCKFILE_EXCLUSIVE_LOCK, offset, len)
+}
+
+/// Tries to exclusively lock a byte range `[offset, offset + len)` of the
+/// file, returning as soon as possible if already locked. A `len` of `0`
+/// locks from `offset` to the end of the file.
+pub fn try_lock_range(
+    handle: FileDesc,
+    offset: u64,
+    len: u64,
+) -> Result<bool, Error> {
+    try_lock_file(handle, LOCKFILE_EXCLUSIVE_LOCK, offset, len)
+}
+
+/// Locks a byte range `[offset, offset + len)` of the file with a shared
+/// (read) lock and blocks until it is possible to lock. A `len` of `0` locks
+/// from `offset` to the end of the file.
+pub fn lock_range_shared(
+    handle: FileDesc,
+    offset: u64,
+    len: u64,
+) -> Result<(), Error> {
+    lock_file(handle, 0, offset, len)
+}
+
+/// Tries to lock a byte range `[offset, offset + len)` of the file with a
+/// shared (read) lock, returning as soon as possible if already exclusively
+/// locked. A `len` of `0` locks from `offset` to the end of the file.
+pub fn try_lock_range_shared(
+    handle: FileDesc,
+    offset: u64,
+    len: u64,
+) -> Result<bool, Error> {
+    try_lock_file(handle, 0, offset, len)
+}
+
 /// Unlocks the file.
 pub fn unlock(handle: FileDesc) -> Result<(), Error> {
+    unlock_range(handle, 0, 0)
+}
+
+/// Unlocks the byte range `[offset, offset + len)` of the file. A `len` of
+/// `0` unlocks from `offset` to the end of the file.
+pub fn unlock_range(
+    handle: FileDesc,
+    offset: u64,
+    len: u64,
+) -> Result<(), Error> {
+    let (offset_low, offset_high, len_low, len_high) =
+        split_range(offset, len);
     let mut overlapped: OVERLAPPED =
         unsafe { MaybeUninit::zeroed().assume_init() };
     unsafe {
-        overlapped.u.s_mut().Offset = 0;
-        overlapped.u.s_mut().OffsetHigh = 0;
+        overlapped.u.s_mut().Offset = offset_low;
+        overlapped.u.s_mut().OffsetHigh = offset_high;
     }
     overlapped.hEvent = ptr::null_mut();
 
@@ -225,8 +416,8 @@ pub fn unlock(handle: FileDesc) -> Result<(), Error> {
         UnlockFileEx(
             handle,
             0,
-            DWORD::max_value(),
-            DWORD::max_value(),
+            len_low,
+            len_high,
             &mut overlapped as LPOVERLAPPED,
         )
     };
@@ -242,11 +433,133 @@ pub fn unlock(handle: FileDesc) -> Result<(), Error> {
     }
 }
 
-/// Removes a file. Path must not contain a nul-byte in the middle, but a
-/// nul-byte in the end (and only in the end) is allowed, which in this case no
-/// extra allocation will be made. Otherwise, an extra allocation is made.
+/// Blocks until the file can be locked exclusively, or returns `Ok(false)`
+/// once `timeout` has elapsed without acquiring the lock. Issues a
+/// non-blocking-by-event `LockFileEx` (no `LOCKFILE_FAIL_IMMEDIATELY`) backed
+/// by an auto-reset event in `OVERLAPPED::hEvent`, then waits on that event
+/// with `WaitForSingleObject`; on `WAIT_TIMEOUT` the pending lock request is
+/// cancelled with `CancelIoEx`, and `GetOverlappedResult` is then used to
+/// check whether the lock was actually granted in the race between the wait
+/// timing out and the cancellation taking effect -- `CancelIoEx` does not
+/// undo a lock that already completed, so that race must be observed rather
+/// than assumed away, or a timeout could report `Ok(false)` while silently
+/// leaving the file locked.
+pub fn lock_with_timeout(
+    handle: FileDesc,
+    timeout: Duration,
+) -> Result<bool, Error> {
+    let event =
+        unsafe { CreateEventW(ptr::null_mut(), FALSE, FALSE, ptr::null()) };
+    if event.is_null() {
+        return Err(Error::last_os_error());
+    }
+
+    let mut overlapped: OVERLAPPED =
+        unsafe { MaybeUninit::zeroed().assume_init() };
+    unsafe {
+        overlapped.u.s_mut().Offset = 0;
+        overlapped.u.s_mut().OffsetHigh = 0;
+    }
+    overlapped.hEvent = event;
+
+    let res = unsafe {
+        LockFileEx(
+            handle,
+            LOCKFILE_EXCLUSIVE_LOCK,
+            0,
+            DWORD::max_value(),
+            DWORD::max_value(),
+            &mut overlapped as LPOVERLAPPED,
+        )
+    };
+
+    let result = if res == TRUE {
+        Ok(true)
+    } else if unsafe { GetLastError() } != ERROR_IO_PENDING {
+        Err(Error::last_os_error())
+    } else {
+        // Clamp one below `DWORD::max_value()`: that exact value is
+        // `INFINITE` to `WaitForSingleObject`, which would turn a
+        // huge-but-finite timeout into an unbounded wait.
+        let millis = (timeout.as_millis().min(u128::from(
+            DWORD::max_value() - 1,
+        ))) as DWORD;
+        match unsafe { WaitForSingleObject(event, millis) } {
+            WAIT_OBJECT_0 => Ok(true),
+            WAIT_TIMEOUT => {
+                unsafe { CancelIoEx(handle, &mut overlapped as LPOVERLAPPED) };
+                let mut transferred: DWORD = 0;
+                let completed = unsafe {
+                    GetOverlappedResult(
+                        handle,
+                        &mut overlapped as LPOVERLAPPED,
+                        &mut transferred,
+                        TRUE,
+                    )
+                };
+                if completed == TRUE {
+                    // The lock was actually granted before the cancellation
+                    // took effect; report it rather than leaking a held
+                    // lock the caller believes it doesn't have.
+                    Ok(true)
+                } else if unsafe { GetLastError() }
+                    == ERROR_OPERATION_ABORTED
+                {
+                    Ok(false)
+                } else {
+                    Err(Error::last_os_error())
+                }
+            },
+            _ => Err(Error::last_os_error()),
+        }
+    };
+
+    unsafe { CloseHandle(event) };
+    result
+}
+
+/// Describes the process currently holding a lock that would conflict with
+/// an exclusive lock, as reported by [`query_lock`]/[`query_lock_range`].
+/// Windows has no equivalent of `fcntl(F_GETLK)`, so these always fail with
+/// an "unsupported" error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockHolder {
+    /// The PID of the process holding the lock.
+    pub pid: DWORD,
+    /// Whether the held lock is shared (read) or exclusive (write).
+    pub shared: bool,
+}
+
+/// Windows has no direct equivalent of `fcntl(F_GETLK)`, so there is no way
+/// to learn which process holds a conflicting lock. Always returns an
+/// "unsupported" error.
+pub fn query_lock(_handle: FileDesc) -> Result<Option<LockHolder>, Error> {
+    Err(Error::from_raw_os_error(ERROR_NOT_SUPPORTED as i32))
+}
+
+/// Windows has no direct equivalent of `fcntl(F_GETLK)`, so there is no way
+/// to learn which process holds a conflicting lock. Always returns an
+/// "unsupported" error.
+pub fn query_lock_range(
+    _handle: FileDesc,
+    _offset: u64,
+    _len: u64,
+) -> Result<Option<LockHolder>, Error> {
+    Err(Error::from_raw_os_error(ERROR_NOT_SUPPORTED as i32))
+}
+
+/// Removes a file. Path must not contain a nul-byte in the middle (an
+/// embedded nul-byte causes this to return an error), but a nul-byte in the
+/// end (and only in the end) is allowed and stripped before encoding.
 pub fn remove(path: &[u8]) -> Result<(), Error> {
-    unimplemented!()
+    let wide_path = to_wide_path(path)?;
+    let res = unsafe { DeleteFileW(wide_path.as_ptr()) };
+
+    if res == TRUE {
+        Ok(())
+    } else {
+        Err(Error::last_os_error())
+    }
 }
 
 /// Closes the file.
@@ -254,4 +567,107 @@ pub fn close(handle: FileDesc) {
     unsafe {
         CloseHandle(handle);
     }
+}
+
+/// Marker type selecting this module's [`LockBackend`](crate::backend::LockBackend)
+/// implementation.
+pub struct Windows;
+
+impl crate::backend::LockBackend for Windows {
+    type FileDesc = FileDesc;
+    type Error = Error;
+    type LockHolder = LockHolder;
+
+    fn open(path: &[u8]) -> Result<Self::FileDesc, Self::Error> {
+        open(path)
+    }
+
+    fn lock(fd: Self::FileDesc) -> Result<(), Self::Error> {
+        lock(fd)
+    }
+
+    fn try_lock(fd: Self::FileDesc) -> Result<bool, Self::Error> {
+        try_lock(fd)
+    }
+
+    fn lock_shared(fd: Self::FileDesc) -> Result<(), Self::Error> {
+        lock_shared(fd)
+    }
+
+    fn try_lock_shared(fd: Self::FileDesc) -> Result<bool, Self::Error> {
+        try_lock_shared(fd)
+    }
+
+    fn lock_range(
+        fd: Self::FileDesc,
+        offset: u64,
+        len: u64,
+    ) -> Result<(), Self::Error> {
+        lock_range(fd, offset, len)
+    }
+
+    fn try_lock_range(
+        fd: Self::FileDesc,
+        offset: u64,
+        len: u64,
+    ) -> Result<bool, Self::Error> {
+        try_lock_range(fd, offset, len)
+    }
+
+    fn lock_range_shared(
+        fd: Self::FileDesc,
+        offset: u64,
+        len: u64,
+    ) -> Result<(), Self::Error> {
+        lock_range_shared(fd, offset, len)
+    }
+
+    fn try_lock_range_shared(
+        fd: Self::FileDesc,
+        offset: u64,
+        len: u64,
+    ) -> Result<bool, Self::Error> {
+        try_lock_range_shared(fd, offset, len)
+    }
+
+    fn unlock(fd: Self::FileDesc) -> Result<(), Self::Error> {
+        unlock(fd)
+    }
+
+    fn unlock_range(
+        fd: Self::FileDesc,
+        offset: u64,
+        len: u64,
+    ) -> Result<(), Self::Error> {
+        unlock_range(fd, offset, len)
+    }
+
+    fn lock_with_timeout(
+        fd: Self::FileDesc,
+        timeout: Duration,
+    ) -> Result<bool, Self::Error> {
+        lock_with_timeout(fd, timeout)
+    }
+
+    fn query_lock(
+        fd: Self::FileDesc,
+    ) -> Result<Option<Self::LockHolder>, Self::Error> {
+        query_lock(fd)
+    }
+
+    fn query_lock_range(
+        fd: Self::FileDesc,
+        offset: u64,
+        len: u64,
+    ) -> Result<Option<Self::LockHolder>, Self::Error> {
+        query_lock_range(fd, offset, len)
+    }
+
+    fn remove(path: &[u8]) -> Result<(), Self::Error> {
+        remove(path)
+    }
+
+    fn close(fd: Self::FileDesc) {
+        close(fd)
+    }
 }
\ No newline at end of file